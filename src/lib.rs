@@ -0,0 +1,1003 @@
+//! Библиотека редукции базиса решётки (LLL / BKZ) с точной рациональной
+//! арифметикой.
+//!
+//! Бинарник в `main.rs` — тонкая обёртка над этим API: он лишь разбирает
+//! аргументы командной строки и вызывает [`reduce`], [`reduce_gram`] или
+//! [`babai_nearest_plane`], чтобы другие
+//! инструменты (например, криптографические) тоже могли подключить
+//! редукцию напрямую и получить не только новый базис, но и унимодулярную
+//! матрицу перехода `U`, для которой `result.basis == U * original`.
+
+use rug::{ops::Pow, Integer, Rational};
+use std::fmt;
+
+pub mod parser;
+
+/// Какой алгоритм редукции запускать.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Lll,
+    Bkz,
+}
+
+/// Параметры запуска редукции.
+#[derive(Debug, Clone)]
+pub struct ReductionParams {
+    pub algorithm: Algorithm,
+    /// Параметр качества редукции в условии Ловаса, обычно `3/4`.
+    pub delta: Rational,
+    /// Размер блока для BKZ; игнорируется для LLL.
+    pub block_size: usize,
+    /// Если `true`, в `ReductionResult::transform` вернётся унимодулярная
+    /// матрица перехода `U`.
+    pub track_transform: bool,
+}
+
+impl ReductionParams {
+    /// Параметры для LLL с заданной `delta`.
+    pub fn lll(delta: Rational) -> Self {
+        ReductionParams {
+            algorithm: Algorithm::Lll,
+            delta,
+            block_size: 2,
+            track_transform: false,
+        }
+    }
+
+    /// Параметры для BKZ с заданными `delta` и размером блока.
+    pub fn bkz(delta: Rational, block_size: usize) -> Self {
+        ReductionParams {
+            algorithm: Algorithm::Bkz,
+            delta,
+            block_size,
+            track_transform: false,
+        }
+    }
+
+    /// Включает или выключает отслеживание матрицы перехода `U`.
+    pub fn with_transform(mut self, track_transform: bool) -> Self {
+        self.track_transform = track_transform;
+        self
+    }
+}
+
+/// Результат редукции: новый базис и, если запрошено, матрица перехода.
+#[derive(Debug, Clone)]
+pub struct ReductionResult {
+    pub basis: Vec<Vec<Integer>>,
+    /// Унимодулярная матрица `U` такая, что `basis == U * original`,
+    /// присутствует только если `params.track_transform` был `true`.
+    pub transform: Option<Vec<Vec<Integer>>>,
+}
+
+/// Редуцирует `basis` согласно `params` и возвращает новый базис (и,
+/// опционально, матрицу перехода).
+pub fn reduce(basis: &[Vec<Integer>], params: ReductionParams) -> ReductionResult {
+    let mut b = basis.to_vec();
+
+    let transform = match params.algorithm {
+        Algorithm::Lll => lll(&mut b, &params.delta, params.track_transform),
+        Algorithm::Bkz => bkz(&mut b, &params.delta, params.block_size, params.track_transform),
+    };
+
+    ReductionResult {
+        basis: b,
+        transform,
+    }
+}
+
+/// Результат редукции, заданной матрицей Грама: редуцированная матрица
+/// Грама `U * gram * U^T` и сама унимодулярная матрица перехода `U`.
+#[derive(Debug, Clone)]
+pub struct GramReductionResult {
+    pub gram: Vec<Vec<Integer>>,
+    pub transform: Vec<Vec<Integer>>,
+}
+
+/// Матрица Грама не прошла валидацию: не квадратная или не симметричная.
+/// `compute_gram_schmidt_from_gram`/`gram_size_reduce` читают `g[j][l]` в
+/// обоих треугольниках матрицы, полагаясь на это как на инвариант, так что
+/// проверка выполняется один раз на входе, а не по месту использования.
+#[derive(Debug, Clone)]
+pub struct GramError(String);
+
+impl fmt::Display for GramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for GramError {}
+
+fn validate_gram(gram: &[Vec<Integer>]) -> Result<(), GramError> {
+    let n = gram.len();
+    for (i, row) in gram.iter().enumerate() {
+        if row.len() != n {
+            return Err(GramError(format!(
+                "матрица Грама должна быть квадратной: строка {i} имеет длину {}, ожидалось {n}",
+                row.len()
+            )));
+        }
+    }
+    for i in 0..n {
+        for j in (i + 1)..n {
+            if gram[i][j] != gram[j][i] {
+                return Err(GramError(format!(
+                    "матрица Грама должна быть симметричной: g[{i}][{j}] != g[{j}][{i}]"
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Редуцирует решётку, заданную только матрицей Грама `gram` (без явных
+/// координат векторов): `compute_gram_schmidt` и условие Ловаса используют
+/// лишь скалярные произведения `<b_i, b_j>`, так что весь алгоритм работает
+/// непосредственно с `G`, обновляя её под те же операции вычитания/обмена,
+/// что и явный базис. Матрица перехода `U` отслеживается всегда, независимо
+/// от `params.track_transform`: без координат только она связывает
+/// редуцированную матрицу Грама с исходным базисом.
+///
+/// Возвращает ошибку, если `gram` не квадратная или не симметричная —
+/// `compute_gram_schmidt_from_gram`/`gram_size_reduce` полагаются на оба
+/// этих свойства и иначе либо выйдут за границы массива, либо молча
+/// вернут результат для неверно интерпретированной решётки.
+pub fn reduce_gram(
+    gram: &[Vec<Integer>],
+    params: ReductionParams,
+) -> Result<GramReductionResult, GramError> {
+    validate_gram(gram)?;
+    let mut g = gram.to_vec();
+
+    let transform = match params.algorithm {
+        Algorithm::Lll => lll_gram(&mut g, &params.delta, true),
+        Algorithm::Bkz => bkz_gram(&mut g, &params.delta, params.block_size, true),
+    }
+    .expect("lll_gram/bkz_gram с track_transform=true всегда возвращают матрицу");
+
+    Ok(GramReductionResult { gram: g, transform })
+}
+
+// --- Вспомогательные функции для векторов Integer ---
+
+fn subtract_vec(v1: &[Integer], v2: &[Integer]) -> Vec<Integer> {
+    v1.iter()
+        .zip(v2.iter())
+        .map(|(a, b)| Integer::from(a - b)) // Явное преобразование
+        .collect()
+}
+
+fn scalar_mul(scalar: &Integer, v: &[Integer]) -> Vec<Integer> {
+    v.iter()
+        .map(|x| Integer::from(scalar * x)) // Явное преобразование
+        .collect()
+}
+
+fn subtract_vec_rational(v1: &[Rational], v2: &[Rational]) -> Vec<Rational> {
+    v1.iter()
+        .zip(v2.iter())
+        .map(|(a, b)| a.clone() - b.clone()) // Операции для типа Rational
+        .collect()
+}
+
+/// Матрица `n x n` с единицами на диагонали.
+fn identity_matrix(n: usize) -> Vec<Vec<Integer>> {
+    (0..n)
+        .map(|i| (0..n).map(|j| Integer::from((i == j) as i32)).collect())
+        .collect()
+}
+
+/// Произведение матриц `a * b` над `Integer`.
+fn matmul(a: &[Vec<Integer>], b: &[Vec<Integer>]) -> Vec<Vec<Integer>> {
+    let n = a.len();
+    let p = b.len();
+    let m = if p == 0 { 0 } else { b[0].len() };
+    (0..n)
+        .map(|i| {
+            (0..m)
+                .map(|j| {
+                    let mut sum = Integer::new();
+                    for k in 0..p {
+                        sum += Integer::from(&a[i][k] * &b[k][j]);
+                    }
+                    sum
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Вкладывает квадратную матрицу `block` размера `size` в единичную
+/// матрицу `full_n x full_n` на позиции `(start, start)`.
+fn embed_block(full_n: usize, start: usize, size: usize, block: &[Vec<Integer>]) -> Vec<Vec<Integer>> {
+    let mut m = identity_matrix(full_n);
+    for i in 0..size {
+        for j in 0..size {
+            m[start + i][start + j] = block[i][j].clone();
+        }
+    }
+    m
+}
+
+// --- Основные алгоритмы ---
+
+/// Вычисляет ортогональный базис Грама-Шмидта (b_star) и коэффициенты mu.
+/// Это вынесено в отдельную функцию, чтобы избежать дублирования кода в LLL и BKZ.
+fn compute_gram_schmidt(b: &[Vec<Integer>]) -> (Vec<Vec<Rational>>, Vec<Vec<Rational>>) {
+    let n = b.len();
+    let mut b_star: Vec<Vec<Rational>> = Vec::with_capacity(n);
+    let mut mu = vec![vec![Rational::new(); n]; n];
+
+    for i in 0..n {
+        let mut b_i_rational: Vec<Rational> = b[i].iter().map(Rational::from).collect();
+        for j in 0..i {
+            // mu[i][j] = <b_i, b*_j> / <b*_j, b*_j>
+            let num: Rational = b[i].iter().zip(b_star[j].iter()).map(|(bi, bs)| Rational::from(bi) * bs).sum();
+            let den: Rational = b_star[j].iter().map(|c| c.clone().pow(2)).sum();
+
+            mu[i][j] = if den.is_zero() { Rational::new() } else { num / den };
+
+            let mu_b_star: Vec<Rational> = b_star[j].iter().map(|c| mu[i][j].clone() * c).collect();
+            b_i_rational = b_i_rational.iter().zip(mu_b_star.iter()).map(|(a, b)| Rational::from(a - b)).collect();
+        }
+        b_star.push(b_i_rational);
+    }
+    (b_star, mu)
+}
+
+/// Редуцирует строку `k` относительно строки `j`: `q = round(mu[k][j])`,
+/// `b[k] -= q*b[j]` (и, если отслеживается, `u[k] -= q*u[j]`), и обновляет
+/// `mu[k][l]` для `l <= j` без полного пересчёта Грама-Шмидта.
+fn size_reduce(
+    b: &mut [Vec<Integer>],
+    mu: &mut [Vec<Rational>],
+    u: &mut Option<Vec<Vec<Integer>>>,
+    k: usize,
+    j: usize,
+) {
+    let mu_kj = mu[k][j].clone();
+    if mu_kj.clone().abs() <= Rational::from((1, 2)) {
+        return;
+    }
+    let q = mu_kj.round();
+    let q_integer = q.numer().clone();
+    let q_rational = Rational::from(q_integer.clone());
+
+    b[k] = subtract_vec(&b[k], &scalar_mul(&q_integer, &b[j]));
+    if let Some(u) = u {
+        u[k] = subtract_vec(&u[k], &scalar_mul(&q_integer, &u[j]));
+    }
+    for l in 0..j {
+        let delta = q_rational.clone() * mu[j][l].clone();
+        mu[k][l] -= delta;
+    }
+    mu[k][j] -= q_rational;
+}
+
+/// LLL-редукция с использованием точной арифметики. Если `track_transform`
+/// установлен, возвращает унимодулярную матрицу `U` (начатую с единичной и
+/// обновляемую теми же операциями вычитания/обмена, что и `b`), такую что
+/// итоговый `b == U * исходный b`.
+///
+/// `mu` и квадраты норм Грама-Шмидта `big_b[i] = <b*_i, b*_i>`
+/// поддерживаются инкрементально: каждый size reduction и обмен Ловаса
+/// обновляют только затронутые коэффициенты, без полного пересчёта
+/// `compute_gram_schmidt`.
+fn lll(b: &mut Vec<Vec<Integer>>, delta: &Rational, track_transform: bool) -> Option<Vec<Vec<Integer>>> {
+    let n = b.len();
+    let mut u = if track_transform { Some(identity_matrix(n)) } else { None };
+    if n == 0 {
+        return u;
+    }
+
+    let (b_star, mut mu) = compute_gram_schmidt(b);
+    let mut big_b: Vec<Rational> = b_star
+        .iter()
+        .map(|v| v.iter().map(|c| c.clone().pow(2)).sum())
+        .collect();
+
+    let mut k = 1;
+    while k < n {
+        // Size reduction строки k относительно всех предыдущих строк.
+        for j in (0..k).rev() {
+            size_reduce(b, &mut mu, &mut u, k, j);
+        }
+
+        // Условие Ловаса.
+        if big_b[k - 1].is_zero() {
+            k += 1;
+            continue;
+        }
+
+        if big_b[k] >= (delta.clone() - mu[k][k - 1].clone().pow(2)) * big_b[k - 1].clone() {
+            k += 1;
+        } else {
+            // Обмен Ловаса: обновляем mu и big_b на месте вместо пересчёта GS.
+            let m = mu[k][k - 1].clone();
+            let b_new = big_b[k].clone() + m.clone() * m.clone() * big_b[k - 1].clone();
+
+            if b_new.is_zero() {
+                mu[k][k - 1] = Rational::new();
+                big_b[k] = Rational::new();
+            } else {
+                mu[k][k - 1] = m.clone() * big_b[k - 1].clone() / b_new.clone();
+                big_b[k] = big_b[k - 1].clone() * big_b[k].clone() / b_new.clone();
+            }
+            big_b[k - 1] = b_new;
+
+            b.swap(k, k - 1);
+            if let Some(u) = u.as_mut() {
+                u.swap(k, k - 1);
+            }
+            for l in 0..(k - 1) {
+                let tmp = mu[k - 1][l].clone();
+                mu[k - 1][l] = mu[k][l].clone();
+                mu[k][l] = tmp;
+            }
+            for i in (k + 1)..n {
+                let t = mu[i][k].clone();
+                mu[i][k] = mu[i][k - 1].clone() - m.clone() * t.clone();
+                mu[i][k - 1] = t + mu[k][k - 1].clone() * mu[i][k].clone();
+            }
+
+            k = std::cmp::max(1, k - 1);
+        }
+    }
+
+    u
+}
+
+/// Энумерация Шнорра-Эйхлера: ищет целочисленный вектор коэффициентов
+/// `x` (длины `beta`), минимизирующий `sum_i B[i] * (x_i + sum_{j>i} mu[j][i] x_j)^2`,
+/// то есть квадрат нормы `sum_i x_i b*_i` в проецированной решётке блока.
+/// Возвращает `None`, если не найден нетривиальный вектор короче `b*_0`.
+fn enumerate_block(mu: &[Vec<Rational>], big_b: &[Rational], beta: usize) -> Option<Vec<Integer>> {
+    if beta == 0 || big_b[0].is_zero() {
+        return None;
+    }
+    let mut x = vec![Integer::new(); beta];
+    let mut best: Option<Vec<Integer>> = None;
+    let mut bound = big_b[0].clone();
+    enumerate_level(mu, big_b, beta, beta as isize - 1, Rational::new(), &mut x, &mut best, &mut bound);
+    best
+}
+
+/// Один уровень рекурсии энумерации: выбирает `x[i]` в зигзаг-порядке
+/// вокруг `center = round(-sum_{j>i} mu[j][i] x_j)` (`v, v+1, v-1, v+2, ...`),
+/// отсекая ветви, чья частичная норма уже не меньше текущего лучшего `bound`.
+fn enumerate_level(
+    mu: &[Vec<Rational>],
+    big_b: &[Rational],
+    beta: usize,
+    i: isize,
+    partial: Rational,
+    x: &mut Vec<Integer>,
+    best: &mut Option<Vec<Integer>>,
+    bound: &mut Rational,
+) {
+    if i < 0 {
+        if x.iter().any(|c| *c != 0) {
+            *bound = partial;
+            *best = Some(x.clone());
+        }
+        return;
+    }
+    let idx = i as usize;
+
+    let mut center = Rational::new();
+    for j in (idx + 1)..beta {
+        center -= mu[j][idx].clone() * Rational::from(x[j].clone());
+    }
+
+    if big_b[idx].is_zero() {
+        // Вырожденное (линейно зависимое) направление: пробуем только центр.
+        x[idx] = center.round().numer().clone();
+        enumerate_level(mu, big_b, beta, i - 1, partial, x, best, bound);
+        return;
+    }
+
+    let center_int = center.clone().round().numer().clone();
+    let remaining = bound.clone() - partial.clone();
+    if remaining <= Rational::new() {
+        return;
+    }
+    // Радиус перебора оценивается через f64 только для границ цикла; каждое
+    // кандидатное значение всё равно проверяется точным сравнением Rational.
+    let radius = (remaining.to_f64() / big_b[idx].to_f64()).sqrt().floor() as i64 + 1;
+
+    for step in 0..=radius {
+        let offsets: Vec<i64> = if step == 0 { vec![0] } else { vec![step, -step] };
+        for offset in &offsets {
+            let cand = Integer::from(&center_int + *offset);
+            let diff = Rational::from(cand.clone()) - center.clone();
+            let term = big_b[idx].clone() * diff.clone() * diff;
+            let new_partial = partial.clone() + term;
+            if new_partial < *bound {
+                x[idx] = cand;
+                enumerate_level(mu, big_b, beta, i - 1, new_partial, x, best, bound);
+            }
+        }
+    }
+}
+
+/// BKZ-редукция по схеме Шнорра-Эйхлера: для каждого окна `[k, k+beta)`
+/// ищет энумерацией кратчайший вектор в проецированной подрешётке блока
+/// и, если он короче `sqrt(delta) * ||b*_k||`, вставляет соответствующую
+/// целочисленную комбинацию `sum v_i b_{k+i}` в позицию `k`, после чего
+/// LLL восстанавливает базис блока. Тур по всем `k` повторяется, пока он
+/// не перестанет менять базис или не будет достигнут `max_iters`. Если
+/// `track_transform` установлен, возвращает унимодулярную `U` такую, что
+/// итоговый `b == U * исходный b`.
+fn bkz(b: &mut Vec<Vec<Integer>>, delta: &Rational, block_size: usize, track_transform: bool) -> Option<Vec<Vec<Integer>>> {
+    let n = b.len();
+    let mut u = if track_transform { Some(identity_matrix(n)) } else { None };
+    if n == 0 {
+        return u;
+    }
+
+    if let Some(lll_u) = lll(b, delta, track_transform) {
+        if let Some(u) = u.as_mut() {
+            *u = matmul(&lll_u, u);
+        }
+    }
+
+    let dim = b[0].len();
+    let max_iters = 2 * n;
+    let mut iter_count = 0;
+
+    loop {
+        iter_count += 1;
+        let mut tour_changed = false;
+
+        for k in 0..n.saturating_sub(1) {
+            let beta = block_size.min(n - k);
+            if beta < 2 {
+                continue;
+            }
+
+            let (b_star, mu) = compute_gram_schmidt(b);
+            let big_b: Vec<Rational> = b_star.iter().map(|v| v.iter().map(|c| c.clone().pow(2)).sum()).collect();
+
+            let local_mu: Vec<Vec<Rational>> = (0..beta)
+                .map(|i| (0..beta).map(|j| mu[k + i][k + j].clone()).collect())
+                .collect();
+            let local_b: Vec<Rational> = big_b[k..k + beta].to_vec();
+
+            let coeffs = match enumerate_block(&local_mu, &local_b, beta) {
+                Some(c) => c,
+                None => continue,
+            };
+
+            // Норма проекции найденного вектора в исходных (не локальных) координатах блока.
+            let proj_norm: Rational = (0..beta)
+                .map(|i| {
+                    let mut coeff_contrib = Rational::from(coeffs[i].clone());
+                    for j in (i + 1)..beta {
+                        coeff_contrib += local_mu[j][i].clone() * Rational::from(coeffs[j].clone());
+                    }
+                    local_b[i].clone() * coeff_contrib.clone() * coeff_contrib
+                })
+                .sum();
+
+            if proj_norm >= delta.clone() * big_b[k].clone() {
+                continue;
+            }
+
+            let mut new_vec = vec![Integer::new(); dim];
+            for i in 0..beta {
+                if coeffs[i] != 0 {
+                    let term = scalar_mul(&coeffs[i], &b[k + i]);
+                    new_vec = new_vec.iter().zip(term.iter()).map(|(a, c)| Integer::from(a + c)).collect();
+                }
+            }
+
+            // [new_vec, b[k], ..., b[k+beta-1]] линейно зависимы (ранг beta при
+            // beta+1 векторах): LLL-редукция этого списка обнулит один из них.
+            let mut extended: Vec<Vec<Integer>> = Vec::with_capacity(beta + 1);
+            extended.push(new_vec);
+            extended.extend(b[k..k + beta].iter().cloned());
+
+            let ext_u = lll(&mut extended, delta, true).expect("lll с track_transform=true всегда возвращает матрицу");
+
+            let zero_pos = match extended.iter().position(|v| v.iter().all(|c| c.is_zero())) {
+                Some(p) => p,
+                None => continue, // экстремально маловероятно: вставка не укоротила базис блока
+            };
+
+            let mut new_block = Vec::with_capacity(beta);
+            let mut local_transform = Vec::with_capacity(beta);
+            for (idx, row) in extended.iter().enumerate() {
+                if idx == zero_pos {
+                    continue;
+                }
+                new_block.push(row.clone());
+                // transform_row выражает новую строку через [new_vec, b[k..k+beta)];
+                // раскрываем new_vec = sum coeffs[j] * b[k+j], чтобы получить
+                // коэффициенты прямо в исходном базисе блока.
+                let transform_row = &ext_u[idx];
+                let w = &transform_row[0];
+                let row_in_block: Vec<Integer> = (0..beta)
+                    .map(|j| {
+                        let contribution = Integer::from(w * &coeffs[j]);
+                        Integer::from(&transform_row[j + 1] + &contribution)
+                    })
+                    .collect();
+                local_transform.push(row_in_block);
+            }
+
+            if new_block.len() != beta {
+                // Более одного нулевого вектора — вырожденный случай, блок не трогаем.
+                continue;
+            }
+
+            for i in 0..beta {
+                b[k + i] = new_block[i].clone();
+            }
+            if let Some(u) = u.as_mut() {
+                let step = embed_block(n, k, beta, &local_transform);
+                *u = matmul(&step, u);
+            }
+
+            tour_changed = true;
+        }
+
+        if !tour_changed || iter_count >= max_iters {
+            break;
+        }
+    }
+
+    u
+}
+
+/// Решает CVP приближённо алгоритмом ближайшей плоскости Бабая: находит
+/// точку решётки, порождённой `reduced_basis`, ближайшую к `target`.
+/// Качество ответа зависит от качества редукции базиса, поэтому функцию
+/// естественно вызывать после [`reduce`] с `Algorithm::Lll`/`Algorithm::Bkz`.
+pub fn babai_nearest_plane(reduced_basis: &[Vec<Integer>], target: &[Integer]) -> Vec<Integer> {
+    let n = reduced_basis.len();
+    if n == 0 {
+        return Vec::new();
+    }
+
+    let (b_star, _mu) = compute_gram_schmidt(reduced_basis);
+    let mut w: Vec<Rational> = target.iter().map(Rational::from).collect();
+    let mut closest = vec![Integer::new(); reduced_basis[0].len()];
+
+    for i in (0..n).rev() {
+        let norm_sq: Rational = b_star[i].iter().map(|c| c.clone().pow(2)).sum();
+        if norm_sq.is_zero() {
+            continue;
+        }
+        let inner: Rational = w.iter().zip(b_star[i].iter()).map(|(a, c)| a.clone() * c.clone()).sum();
+        let c = (inner / norm_sq).round();
+        let c_integer = c.numer().clone();
+        if c_integer != 0 {
+            let term = scalar_mul(&c_integer, &reduced_basis[i]);
+            closest = closest.iter().zip(term.iter()).map(|(a, b)| Integer::from(a + b)).collect();
+            let term_rational: Vec<Rational> = term.iter().map(Rational::from).collect();
+            w = subtract_vec_rational(&w, &term_rational);
+        }
+    }
+
+    closest
+}
+
+// --- Редукция по матрице Грама (без явных координат базиса) ---
+
+/// Вычисляет `big_b[i] = <b*_i, b*_i>` и `mu` прямо из матрицы Грама `g`,
+/// по той же рекурсии, что и [`compute_gram_schmidt`], но выражая
+/// `<b_i, b*_j>` через `g[i][j]` вместо скалярного произведения координат.
+fn compute_gram_schmidt_from_gram(g: &[Vec<Integer>]) -> (Vec<Rational>, Vec<Vec<Rational>>) {
+    let n = g.len();
+    let mut mu = vec![vec![Rational::new(); n]; n];
+    let mut big_b = vec![Rational::new(); n];
+    // num[i][j] = <b_i, b*_j> для i >= j.
+    let mut num = vec![vec![Rational::new(); n]; n];
+
+    for j in 0..n {
+        for i in j..n {
+            let mut val = Rational::from(&g[i][j]);
+            for l in 0..j {
+                val -= mu[j][l].clone() * num[i][l].clone();
+            }
+            num[i][j] = val;
+        }
+        big_b[j] = num[j][j].clone();
+        if !big_b[j].is_zero() {
+            for i in (j + 1)..n {
+                mu[i][j] = num[i][j].clone() / big_b[j].clone();
+            }
+        }
+    }
+
+    (big_b, mu)
+}
+
+/// Размерно-редуцирует строку/столбец `k` относительно `j` в матрице Грама:
+/// эквивалент `b[k] -= q*b[j]`, выраженный через `g[k][l] -= q*g[j][l]`
+/// (и симметрично по столбцу), `g[k][k] += q^2*g[j][j] - 2*q*g[k][j]`.
+/// Обновление `mu`/`u` — то же самое, что и в [`size_reduce`].
+fn gram_size_reduce(
+    g: &mut [Vec<Integer>],
+    mu: &mut [Vec<Rational>],
+    u: &mut Option<Vec<Vec<Integer>>>,
+    k: usize,
+    j: usize,
+) {
+    let mu_kj = mu[k][j].clone();
+    if mu_kj.clone().abs() <= Rational::from((1, 2)) {
+        return;
+    }
+    let q = mu_kj.round();
+    let q_integer = q.numer().clone();
+    let q_rational = Rational::from(q_integer.clone());
+
+    let n = g.len();
+    let g_kj = g[k][j].clone();
+    let g_jj = g[j][j].clone();
+    for l in 0..n {
+        if l == k {
+            continue;
+        }
+        let delta = Integer::from(&q_integer * &g[j][l]);
+        g[k][l] -= delta;
+        g[l][k] = g[k][l].clone();
+    }
+    let cross = Integer::from(&q_integer * &g_kj);
+    let mut kk = g[k][k].clone();
+    kk -= cross.clone();
+    kk -= cross;
+    kk += Integer::from(&q_integer * &q_integer) * &g_jj;
+    g[k][k] = kk;
+
+    if let Some(u) = u {
+        u[k] = subtract_vec(&u[k], &scalar_mul(&q_integer, &u[j]));
+    }
+    for l in 0..j {
+        let delta_mu = q_rational.clone() * mu[j][l].clone();
+        mu[k][l] -= delta_mu;
+    }
+    mu[k][j] -= q_rational;
+}
+
+/// LLL-редукция матрицы Грама: то же самое, что [`lll`], но без явных
+/// координат базиса. Обмен Ловаса строк `k`/`k-1` здесь соответствует
+/// перестановке строки и столбца `k`/`k-1` в `g` (поскольку `G` симметрична,
+/// это в точности переиндексация базисных векторов); обновление `mu`/`big_b`
+/// — те же формулы, что и в [`lll`].
+fn lll_gram(g: &mut Vec<Vec<Integer>>, delta: &Rational, track_transform: bool) -> Option<Vec<Vec<Integer>>> {
+    let n = g.len();
+    let mut u = if track_transform { Some(identity_matrix(n)) } else { None };
+    if n == 0 {
+        return u;
+    }
+
+    let (mut big_b, mut mu) = compute_gram_schmidt_from_gram(g);
+
+    let mut k = 1;
+    while k < n {
+        for j in (0..k).rev() {
+            gram_size_reduce(g, &mut mu, &mut u, k, j);
+        }
+
+        if big_b[k - 1].is_zero() {
+            k += 1;
+            continue;
+        }
+
+        if big_b[k] >= (delta.clone() - mu[k][k - 1].clone().pow(2)) * big_b[k - 1].clone() {
+            k += 1;
+        } else {
+            g.swap(k, k - 1);
+            for row in g.iter_mut() {
+                row.swap(k, k - 1);
+            }
+            if let Some(u) = u.as_mut() {
+                u.swap(k, k - 1);
+            }
+
+            let m = mu[k][k - 1].clone();
+            let b_new = big_b[k].clone() + m.clone() * m.clone() * big_b[k - 1].clone();
+
+            if b_new.is_zero() {
+                mu[k][k - 1] = Rational::new();
+                big_b[k] = Rational::new();
+            } else {
+                mu[k][k - 1] = m.clone() * big_b[k - 1].clone() / b_new.clone();
+                big_b[k] = big_b[k - 1].clone() * big_b[k].clone() / b_new.clone();
+            }
+            big_b[k - 1] = b_new;
+
+            for l in 0..(k - 1) {
+                let tmp = mu[k - 1][l].clone();
+                mu[k - 1][l] = mu[k][l].clone();
+                mu[k][l] = tmp;
+            }
+            for i in (k + 1)..n {
+                let t = mu[i][k].clone();
+                mu[i][k] = mu[i][k - 1].clone() - m.clone() * t.clone();
+                mu[i][k - 1] = t + mu[k][k - 1].clone() * mu[i][k].clone();
+            }
+
+            k = std::cmp::max(1, k - 1);
+        }
+    }
+
+    u
+}
+
+/// BKZ-редукция матрицы Грама по той же схеме Шнорра-Эйхлера, что и [`bkz`].
+/// Энумерация ([`enumerate_block`]) работает одинаково в обоих случаях, так
+/// как ей нужны только `mu`/`big_b`; отличается лишь то, как строится и
+/// вставляется найденный вектор `w = sum v_i b_{k+i}` — здесь его скалярные
+/// произведения с остальным базисом получаются прямо из `g`, без координат:
+/// `<w, b_l> = sum_i v_i * g[k+i][l]`, `<w, w> = sum_i sum_j v_i v_j g[k+i][k+j]`.
+fn bkz_gram(g: &mut Vec<Vec<Integer>>, delta: &Rational, block_size: usize, track_transform: bool) -> Option<Vec<Vec<Integer>>> {
+    let n = g.len();
+    let mut u = if track_transform { Some(identity_matrix(n)) } else { None };
+    if n == 0 {
+        return u;
+    }
+
+    if let Some(lll_u) = lll_gram(g, delta, track_transform) {
+        if let Some(u) = u.as_mut() {
+            *u = matmul(&lll_u, u);
+        }
+    }
+
+    let max_iters = 2 * n;
+    let mut iter_count = 0;
+
+    loop {
+        iter_count += 1;
+        let mut tour_changed = false;
+
+        for k in 0..n.saturating_sub(1) {
+            let beta = block_size.min(n - k);
+            if beta < 2 {
+                continue;
+            }
+
+            let (big_b, mu) = compute_gram_schmidt_from_gram(g);
+            let local_mu: Vec<Vec<Rational>> = (0..beta)
+                .map(|i| (0..beta).map(|j| mu[k + i][k + j].clone()).collect())
+                .collect();
+            let local_b: Vec<Rational> = big_b[k..k + beta].to_vec();
+
+            let coeffs = match enumerate_block(&local_mu, &local_b, beta) {
+                Some(c) => c,
+                None => continue,
+            };
+
+            let proj_norm: Rational = (0..beta)
+                .map(|i| {
+                    let mut coeff_contrib = Rational::from(coeffs[i].clone());
+                    for j in (i + 1)..beta {
+                        coeff_contrib += local_mu[j][i].clone() * Rational::from(coeffs[j].clone());
+                    }
+                    local_b[i].clone() * coeff_contrib.clone() * coeff_contrib
+                })
+                .sum();
+
+            if proj_norm >= delta.clone() * big_b[k].clone() {
+                continue;
+            }
+
+            // Расширенная матрица Грама [w, b_k, ..., b_{k+beta-1}], построенная
+            // напрямую из g и coeffs — без единой координаты базисного вектора.
+            let mut ext_g = vec![vec![Integer::new(); beta + 1]; beta + 1];
+            for i in 0..beta {
+                for j in 0..beta {
+                    ext_g[i + 1][j + 1] = g[k + i][k + j].clone();
+                }
+            }
+            for l in 0..beta {
+                let mut val = Integer::new();
+                for i in 0..beta {
+                    if coeffs[i] != 0 {
+                        val += Integer::from(&coeffs[i] * &g[k + i][k + l]);
+                    }
+                }
+                ext_g[0][l + 1] = val.clone();
+                ext_g[l + 1][0] = val;
+            }
+            let mut ww = Integer::new();
+            for i in 0..beta {
+                if coeffs[i] == 0 {
+                    continue;
+                }
+                for j in 0..beta {
+                    if coeffs[j] == 0 {
+                        continue;
+                    }
+                    ww += Integer::from(&coeffs[i] * &coeffs[j]) * &g[k + i][k + j];
+                }
+            }
+            ext_g[0][0] = ww;
+
+            let ext_u = lll_gram(&mut ext_g, delta, true).expect("lll_gram с track_transform=true всегда возвращает матрицу");
+
+            let zero_pos = match (0..beta + 1).find(|&idx| ext_g[idx][idx].is_zero()) {
+                Some(p) => p,
+                None => continue,
+            };
+
+            let mut local_transform = Vec::with_capacity(beta);
+            for idx in 0..(beta + 1) {
+                if idx == zero_pos {
+                    continue;
+                }
+                let transform_row = &ext_u[idx];
+                let w = &transform_row[0];
+                let row_in_block: Vec<Integer> = (0..beta)
+                    .map(|j| {
+                        let contribution = Integer::from(w * &coeffs[j]);
+                        Integer::from(&transform_row[j + 1] + &contribution)
+                    })
+                    .collect();
+                local_transform.push(row_in_block);
+            }
+
+            if local_transform.len() != beta {
+                // Более одного нулевого вектора — вырожденный случай, блок не трогаем.
+                continue;
+            }
+
+            // Новая подматрица блока: newG[i][j] = sum_{p,q} L[i][p]*L[j][q]*G[k+p][k+q].
+            let mut new_block_g = vec![vec![Integer::new(); beta]; beta];
+            for i in 0..beta {
+                for j in 0..beta {
+                    let mut val = Integer::new();
+                    for p in 0..beta {
+                        if local_transform[i][p] == 0 {
+                            continue;
+                        }
+                        for q in 0..beta {
+                            if local_transform[j][q] == 0 {
+                                continue;
+                            }
+                            val += Integer::from(&local_transform[i][p] * &local_transform[j][q]) * &g[k + p][k + q];
+                        }
+                    }
+                    new_block_g[i][j] = val;
+                }
+            }
+
+            // Скалярные произведения блока с остальным базисом: newRow[i][l] = sum_p L[i][p]*G[k+p][l].
+            for l in (0..n).filter(|&l| l < k || l >= k + beta) {
+                let old_col: Vec<Integer> = (0..beta).map(|p| g[k + p][l].clone()).collect();
+                for i in 0..beta {
+                    let mut val = Integer::new();
+                    for p in 0..beta {
+                        if local_transform[i][p] != 0 {
+                            val += Integer::from(&local_transform[i][p] * &old_col[p]);
+                        }
+                    }
+                    g[k + i][l] = val.clone();
+                    g[l][k + i] = val;
+                }
+            }
+            for i in 0..beta {
+                for j in 0..beta {
+                    g[k + i][k + j] = new_block_g[i][j].clone();
+                }
+            }
+
+            if let Some(u) = u.as_mut() {
+                let step = embed_block(n, k, beta, &local_transform);
+                *u = matmul(&step, u);
+            }
+
+            tour_changed = true;
+        }
+
+        if !tour_changed || iter_count >= max_iters {
+            break;
+        }
+    }
+
+    u
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(values: &[i64]) -> Vec<Integer> {
+        values.iter().map(|&x| Integer::from(x)).collect()
+    }
+
+    /// Регрессия: в `compute_gram_schmidt` деление на ноль для вырожденных
+    /// случаев было защищено явной проверкой, но при обмене Ловаса в `lll`
+    /// такую же защиту забыли. Дублированный базисный вектор делает
+    /// `big_b[k]` и `mu[k][k-1]` одновременно нулями ровно в момент обмена —
+    /// без проверки это паника на делении 0/0.
+    #[test]
+    fn lll_handles_duplicate_basis_vector_without_panicking() {
+        let basis = vec![row(&[1, 0]), row(&[1, 0])];
+        let result = reduce(&basis, ReductionParams::lll(Rational::from((3, 4))));
+        assert_eq!(result.basis.len(), 2);
+    }
+
+    /// LLL-редуцированный 3-мерный базис, в блоке которого `[0, 3)` энумерация
+    /// находит улучшающий вектор `new_vec = b[2] - b[1] = (0, -3, 5)` с
+    /// квадратом нормы `34 < 3/4 * 64` (не специально подобранное вырождение): именно
+    /// такая вставка блока `[beta+1]` векторов ранга `beta` раньше натыкалась
+    /// на деление 0/0 в `lll` при первой же успешной редукции блока в BKZ.
+    /// Проверяем, что результат корректен: `U * original == basis` и базис
+    /// удовлетворяет условию Ловаса/size-reduction.
+    #[test]
+    fn bkz_completes_a_real_block_insertion() {
+        let basis = vec![row(&[8, 0, 0]), row(&[4, 6, 0]), row(&[4, 3, 5])];
+        let delta = Rational::from((3, 4));
+        let result = reduce(
+            &basis,
+            ReductionParams::bkz(delta.clone(), 3).with_transform(true),
+        );
+
+        let transform = result.transform.expect("track_transform=true");
+        assert_eq!(matmul(&transform, &basis), result.basis);
+
+        let (b_star, mu) = compute_gram_schmidt(&result.basis);
+        let big_b: Vec<Rational> = b_star
+            .iter()
+            .map(|v| v.iter().map(|c| c.clone().pow(2)).sum())
+            .collect();
+        for k in 1..big_b.len() {
+            assert!(mu[k][k - 1].clone().abs() <= Rational::from((1, 2)));
+            assert!(
+                big_b[k].clone() >= (delta.clone() - mu[k][k - 1].clone().pow(2)) * big_b[k - 1].clone()
+            );
+        }
+    }
+
+    fn gram_from_basis(basis: &[Vec<Integer>]) -> Vec<Vec<Integer>> {
+        basis
+            .iter()
+            .map(|bi| {
+                basis
+                    .iter()
+                    .map(|bj| bi.iter().zip(bj.iter()).map(|(x, y)| Integer::from(x * y)).sum())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// То же вырождение, что в `lll_handles_duplicate_basis_vector_without_panicking`,
+    /// но через формулировку на матрице Грама: ошибка была продублирована в
+    /// `lll_gram` вместе с самой функцией.
+    #[test]
+    fn lll_gram_handles_duplicate_basis_vector_without_panicking() {
+        let gram = gram_from_basis(&[row(&[1, 0]), row(&[1, 0])]);
+        let result = reduce_gram(&gram, ReductionParams::lll(Rational::from((3, 4))));
+        assert_eq!(result.unwrap().gram.len(), 2);
+    }
+
+    #[test]
+    fn reduce_gram_rejects_non_square_matrix() {
+        let gram = vec![row(&[1, 0]), row(&[0, 1, 0])];
+        let result = reduce_gram(&gram, ReductionParams::lll(Rational::from((3, 4))));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn reduce_gram_rejects_asymmetric_matrix() {
+        let gram = vec![row(&[1, 0]), row(&[1, 1])];
+        let result = reduce_gram(&gram, ReductionParams::lll(Rational::from((3, 4))));
+        assert!(result.is_err());
+    }
+
+    /// Ортогональный базис `[(3,0),(0,1)]` задаёт решётку `{(3a, b)}`; ближайшая
+    /// к `(4, 0)` точка этой решётки — `(3, 0)` (расстояние `1`, тогда как до
+    /// `(6, 0)` — `2`). Коэффициент по первой координате даёт `round(12/9) = 1`
+    /// без округления ровно пополам, так что ответ однозначен независимо от
+    /// направления округления `.5`.
+    #[test]
+    fn babai_nearest_plane_finds_known_closest_vector() {
+        let basis = vec![row(&[3, 0]), row(&[0, 1])];
+        let target = row(&[4, 0]);
+        let closest = babai_nearest_plane(&basis, &target);
+        assert_eq!(closest, row(&[3, 0]));
+    }
+}
@@ -0,0 +1,201 @@
+//! Разбор базиса решётки из текстового представления (JSON или CSV-строка).
+//!
+//! Раньше этим занималась пара хрупких функций на `split`/`panic!`. Здесь же
+//! грамматика базиса описана через `nom`: один токенайзер целых чисел
+//! используется и для JSON-формы (`[["11","3"],["2","11"]]` или
+//! `[[11,3],[2,11]]`), и для CSV-строк, а ошибки возвращаются с позицией в
+//! байтах вместо паники.
+
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::{char, digit1, multispace0};
+use nom::combinator::{all_consuming, map_res, opt, recognize};
+use nom::multi::{separated_list0, separated_list1};
+use nom::sequence::{delimited, pair, preceded, terminated};
+use nom::Finish;
+use nom::IResult;
+use rug::Integer;
+use std::fmt;
+
+/// Ошибка разбора базиса: сообщение плюс смещение в байтах от начала входа.
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ошибка разбора на позиции {}: {}",
+            self.offset, self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl ParseError {
+    fn at(input: &str, remaining: &str, message: impl Into<String>) -> Self {
+        ParseError {
+            offset: input.len() - remaining.len(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Необязательный пробел/перевод строки.
+fn ws(input: &str) -> IResult<&str, &str> {
+    multispace0(input)
+}
+
+/// Целое число произвольной длины с опциональным знаком: `-12`, `+3`, `41`.
+/// Цифры передаются напрямую в `Integer::parse`, так что длина не ограничена.
+fn bare_integer(input: &str) -> IResult<&str, Integer> {
+    map_res(
+        recognize(pair(opt(alt((char('+'), char('-')))), digit1)),
+        |s: &str| Integer::parse(s).map(Integer::from),
+    )(input)
+}
+
+/// То же число, но обёрнутое в кавычки: `"-12"`.
+fn quoted_integer(input: &str) -> IResult<&str, Integer> {
+    delimited(char('"'), bare_integer, char('"'))(input)
+}
+
+/// Одно число строки базиса — либо голое, либо в кавычках.
+fn number(input: &str) -> IResult<&str, Integer> {
+    alt((quoted_integer, bare_integer))(input)
+}
+
+/// Строка базиса: `[1, 2, 3]`, с произвольными пробелами вокруг запятых.
+fn row(input: &str) -> IResult<&str, Vec<Integer>> {
+    delimited(
+        preceded(ws, char('[')),
+        separated_list0(preceded(ws, char(',')), preceded(ws, number)),
+        preceded(ws, char(']')),
+    )(input)
+}
+
+/// Базис целиком: массив строк, например `[["1","2"],["3","4"]]`.
+fn basis(input: &str) -> IResult<&str, Vec<Vec<Integer>>> {
+    delimited(
+        preceded(ws, char('[')),
+        separated_list0(preceded(ws, char(',')), preceded(ws, row)),
+        preceded(ws, char(']')),
+    )(input)
+}
+
+/// Явно разрешаем пустой базис `[]`, чтобы не путать его с пустой строкой.
+fn empty_basis(input: &str) -> IResult<&str, Vec<Vec<Integer>>> {
+    let (rest, _) = preceded(ws, tag("[]"))(input)?;
+    Ok((rest, Vec::new()))
+}
+
+/// Базис обязан быть прямоугольным: все строки одной размерности. Сама
+/// грамматика разбирает строки независимо и не может это проверить, а
+/// дальше по стеку `compute_gram_schmidt` молча обрежет более длинные
+/// строки через `zip` вместо явной ошибки разбора — поэтому проверяем
+/// здесь же, сразу после успешного разбора.
+fn validate_rectangular(rows: &[Vec<Integer>], input: &str) -> Result<(), ParseError> {
+    let dim = match rows.first() {
+        Some(row) => row.len(),
+        None => return Ok(()),
+    };
+    if rows.iter().all(|row| row.len() == dim) {
+        Ok(())
+    } else {
+        Err(ParseError {
+            offset: input.len(),
+            message: "строки базиса должны быть одной длины".to_string(),
+        })
+    }
+}
+
+/// Разбирает базис из строки (JSON-массив массивов). Принимает как
+/// `[["11","3"],["2","11"]]`, так и `[[11,3],[2,11]]`, с произвольными
+/// пробелами/переводами строк между токенами.
+pub fn parse_basis(input: &str) -> Result<Vec<Vec<Integer>>, ParseError> {
+    let mut parse = all_consuming(terminated(alt((empty_basis, basis)), ws));
+    let result = match parse(input).finish() {
+        Ok((_, result)) => result,
+        Err(e) => return Err(ParseError::at(input, e.input, "некорректный формат базиса")),
+    };
+    validate_rectangular(&result, input)?;
+    Ok(result)
+}
+
+/// Разбирает одну строку CSV в вектор целых чисел, используя тот же
+/// токенайзер чисел, что и JSON-форма (ведущие `+`/`-`, цифры любой длины).
+///
+/// Пустая (или состоящая только из пробелов) строка — это ошибка, а не
+/// вектор нулевой длины: иначе случайная пустая строка в файле базиса молча
+/// даёт «рваный» базис, который ниже по стеку развалится на `zip`.
+pub fn parse_csv_row(input: &str) -> Result<Vec<Integer>, ParseError> {
+    let field = delimited(ws, bare_integer, ws);
+    let mut line = all_consuming(separated_list1(char(','), field));
+    match line(input).finish() {
+        Ok((_, row)) => Ok(row),
+        Err(e) => Err(ParseError::at(input, e.input, "некорректное число в строке CSV")),
+    }
+}
+
+/// Разбирает один вектор (например, цель для CVP): `["3","4"]` или `[3,4]`.
+pub fn parse_vector(input: &str) -> Result<Vec<Integer>, ParseError> {
+    match all_consuming(terminated(row, ws))(input).finish() {
+        Ok((_, result)) => Ok(result),
+        Err(e) => Err(ParseError::at(input, e.input, "некорректный формат вектора")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_basis_accepts_quoted_and_bare_json() {
+        let quoted = parse_basis(r#"[["11","3"],["2","11"]]"#).unwrap();
+        let bare = parse_basis("[[11,3],[2,11]]").unwrap();
+        assert_eq!(quoted, bare);
+    }
+
+    #[test]
+    fn parse_basis_tolerates_whitespace_and_newlines() {
+        let result = parse_basis("  [ [ 1 , -2 ] ,\n  [ 3 , 4 ]\n ] \n").unwrap();
+        assert_eq!(result, vec![vec![Integer::from(1), Integer::from(-2)], vec![Integer::from(3), Integer::from(4)]]);
+    }
+
+    #[test]
+    fn parse_basis_accepts_explicit_empty_basis() {
+        assert_eq!(parse_basis("[]").unwrap(), Vec::<Vec<Integer>>::new());
+    }
+
+    #[test]
+    fn parse_basis_rejects_ragged_rows() {
+        assert!(parse_basis("[[1,2],[3]]").is_err());
+    }
+
+    #[test]
+    fn parse_basis_rejects_trailing_garbage() {
+        assert!(parse_basis("[[1,2]] garbage").is_err());
+    }
+
+    #[test]
+    fn parse_csv_row_accepts_signed_integers_with_whitespace() {
+        let result = parse_csv_row(" 1, -2,+3 ").unwrap();
+        assert_eq!(result, vec![Integer::from(1), Integer::from(-2), Integer::from(3)]);
+    }
+
+    #[test]
+    fn parse_csv_row_rejects_empty_line() {
+        assert!(parse_csv_row("").is_err());
+        assert!(parse_csv_row("   ").is_err());
+    }
+
+    #[test]
+    fn parse_vector_accepts_json_array() {
+        let result = parse_vector("[5, -5]").unwrap();
+        assert_eq!(result, vec![Integer::from(5), Integer::from(-5)]);
+    }
+}